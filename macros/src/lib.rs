@@ -0,0 +1,96 @@
+//! `#[derive(LSystemParam)]`, the logical-resource analog of bevy's own
+//! `#[derive(SystemParam)]`. Lives in its own crate (`bevy_rollback_archive_macros`) the same way
+//! `bevy_derive`/`bevy_ecs_macros` live apart from the crates whose derives they implement,
+//! since a proc-macro crate can't also export ordinary items.
+//!
+//! Expands a struct whose fields are all `LRes`/`LResMut`/`LQuery` parameters into the
+//! `SystemParam`/`FetchSystemParam` impls needed to take the whole struct as one system argument,
+//! forwarding each field's `init` (so every field's access-conflict checks still run) and
+//! `get_param`.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericParam, Ident};
+
+#[proc_macro_derive(LSystemParam)]
+pub fn derive_l_system_param(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let struct_name = &input.ident;
+
+    let lifetime = input
+        .generics
+        .params
+        .iter()
+        .find_map(|param| match param {
+            GenericParam::Lifetime(lifetime_def) => Some(lifetime_def.lifetime.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| {
+            panic!(
+                "#[derive(LSystemParam)] requires `{}` to declare a single lifetime, \
+                the same way `LRes`/`LResMut`/`LQuery` do",
+                struct_name
+            )
+        });
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => panic!("#[derive(LSystemParam)] only supports structs with named fields"),
+        },
+        _ => panic!("#[derive(LSystemParam)] only supports structs"),
+    };
+
+    let field_names: Vec<&Ident> = fields
+        .iter()
+        .map(|field| field.ident.as_ref().expect("named field"))
+        .collect();
+    let field_types = fields.iter().map(|field| &field.ty);
+
+    let fetch_name = Ident::new(&format!("Fetch{}", struct_name), Span::call_site());
+
+    let expanded = quote! {
+        #[doc(hidden)]
+        pub struct #fetch_name {
+            _marker: std::marker::PhantomData<(#(<#field_types as bevy::ecs::SystemParam>::Fetch,)*)>,
+        }
+
+        impl<#lifetime> bevy::ecs::SystemParam for #struct_name<#lifetime> {
+            type Fetch = #fetch_name;
+        }
+
+        impl<#lifetime> bevy::ecs::FetchSystemParam<#lifetime> for #fetch_name {
+            type Item = #struct_name<#lifetime>;
+
+            fn init(
+                system_state: &mut bevy::ecs::SystemState,
+                world: &bevy::ecs::World,
+                resources: &mut bevy::ecs::Resources,
+            ) {
+                #(
+                    <<#field_types as bevy::ecs::SystemParam>::Fetch as bevy::ecs::FetchSystemParam>::init(
+                        system_state, world, resources,
+                    );
+                )*
+            }
+
+            #[inline]
+            unsafe fn get_param(
+                system_state: &#lifetime bevy::ecs::SystemState,
+                world: &#lifetime bevy::ecs::World,
+                resources: &#lifetime bevy::ecs::Resources,
+            ) -> Option<Self::Item> {
+                Some(#struct_name {
+                    #(
+                        #field_names: <<#field_types as bevy::ecs::SystemParam>::Fetch as bevy::ecs::FetchSystemParam>::get_param(
+                            system_state, world, resources,
+                        )?,
+                    )*
+                })
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
@@ -1,40 +1,100 @@
 use bevy::prelude::*;
-use bevy::ecs::Command;
+use bevy::ecs::{Command, Bundle, Entity, Component};
 use crate::RollbackBuffer;
 use std::ops::{Deref, DerefMut};
 use std::cell::RefCell;
 
+/// Builds a batch of structural changes (spawn/insert/remove/despawn) against a `RollbackBuffer`'s
+/// `current_world` rather than the live `World`. The builder is stamped with a stable insertion
+/// index at creation time so several builders queued from different systems in the same tick can
+/// later be merged into one deterministically-ordered queue via [`LogicCommands::merge`].
+///
+/// `insertion_index` must come from [`RollbackBuffer::next_logic_system_index`], drawn once per
+/// system at schedule-definition time rather than here at builder-creation time: several systems
+/// run in parallel within the same tick, so an index assigned wherever their threads happen to
+/// reach `LogicCommandsBuilder::new` first isn't guaranteed to land in the same relative order on
+/// a later re-simulation of that same tick during a rollback.
 pub struct LogicCommandsBuilder{
-    pub commands: Commands
+    pub commands: Commands,
+    insertion_index: usize,
 }
 
 impl LogicCommandsBuilder{
-    pub fn new(rollback_buffer: &RollbackBuffer) -> Self{
+    pub fn new(rollback_buffer: &RollbackBuffer, insertion_index: usize) -> Self{
         let mut logic_commands = LogicCommandsBuilder{
-            commands: Commands::default()
+            commands: Commands::default(),
+            insertion_index,
         };
+        // Reserve ids out of the rollback buffer's own `EntityReserver` rather than the live
+        // `World`'s, so entities spawned here get the same ids whether this tick is run fresh or
+        // re-simulated during a rollback.
         logic_commands.commands.set_entity_reserver(rollback_buffer.current_world.get_entity_reserver());
         logic_commands
     }
 
+    /// Spawns a new entity with `bundle`, reserving its id up front from the rollback buffer's
+    /// `EntityReserver`.
+    pub fn spawn(&mut self, bundle: impl Bundle) -> &mut Self{
+        self.commands.spawn(bundle);
+        self
+    }
+
+    /// Inserts `component` onto `entity`.
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> &mut Self{
+        self.commands.insert_one(entity, component);
+        self
+    }
+
+    /// Removes the `T` component from `entity`.
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> &mut Self{
+        self.commands.remove_one::<T>(entity);
+        self
+    }
+
+    /// Despawns `entity`.
+    pub fn despawn(&mut self, entity: Entity) -> &mut Self{
+        self.commands.despawn(entity);
+        self
+    }
+
     pub fn build(self) -> LogicCommands{
         LogicCommands{
-            commands: RefCell::new(self.commands)
+            buffers: vec![(self.insertion_index, RefCell::new(self.commands))],
         }
     }
 }
 
+/// A queue of logical structural changes, possibly merged from several [`LogicCommandsBuilder`]s,
+/// applied to a `RollbackBuffer`'s `current_world`/`current_resources` in a single pass, ordered
+/// by each buffer's insertion index so replays of the same tick are deterministic regardless of
+/// how the systems that queued them were scheduled.
 pub struct LogicCommands{
-    commands: RefCell<Commands>
+    buffers: Vec<(usize, RefCell<Commands>)>,
 }
 
 unsafe impl Send for LogicCommands{}
 unsafe impl Sync for LogicCommands{}
 
+impl LogicCommands{
+    /// Merges several `LogicCommands` (typically one per system that ran this tick) into a
+    /// single queue sorted by insertion index, so the merged queue always applies structural
+    /// changes in the same order no matter what order the originating systems finished in.
+    pub fn merge(commands: impl IntoIterator<Item = LogicCommands>) -> LogicCommands{
+        let mut buffers: Vec<(usize, RefCell<Commands>)> = commands
+            .into_iter()
+            .flat_map(|logic_commands| logic_commands.buffers)
+            .collect();
+        buffers.sort_by_key(|(insertion_index, _)| *insertion_index);
+        LogicCommands{ buffers }
+    }
+}
+
 impl Command for LogicCommands{
     fn write(self: Box<Self>, _world: &mut World, resources: &mut Resources){
         let mut rollback_buffer_r = resources.get_mut::<RollbackBuffer>().expect("Couldn't find RollbackBuffer!");
-        let mut rollback_buffer = rollback_buffer_r.deref_mut();
-        self.commands.borrow_mut().apply(&mut rollback_buffer.current_world, &mut rollback_buffer.current_resources);
+        let rollback_buffer = rollback_buffer_r.deref_mut();
+        for (_, commands) in self.buffers.iter(){
+            commands.borrow_mut().apply(&mut rollback_buffer.current_world, &mut rollback_buffer.current_resources);
+        }
     }
 }
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use crate::{RollbackBuffer, RollbackTracked};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// A component that can be blended a fraction of the way toward a target value. Implemented for
+/// `Transform` so a post-rollback correction can be displayed smoothly instead of snapping the
+/// rendered entity instantly to its resimulated position.
+pub trait Interpolatable: Component + Clone {
+    fn lerp(&self, target: &Self, t: f32) -> Self;
+}
+
+impl Interpolatable for Transform {
+    fn lerp(&self, target: &Self, t: f32) -> Self {
+        Transform {
+            translation: self.translation.lerp(target.translation, t),
+            rotation: self.rotation.slerp(target.rotation, t),
+            scale: self.scale.lerp(target.scale, t),
+        }
+    }
+}
+
+/// How much of the remaining correction to blend out per `UPDATE` frame: `1.0 / ticks_factor`. The
+/// remaining gap shrinks geometrically, so after `ticks_factor` frames up to about 37% of it is
+/// still left; it is closed in one frame only when `ticks_factor` is `1.0`. The fraction does not
+/// depend on how many ticks a rollback resimulated.
+pub(crate) struct CorrectionFactor(pub f32);
+
+/// Per-entity displayed value for an [`Interpolatable`] `T`, kept separate from the authoritative
+/// value simulated in `RollbackBuffer::current_world` so a correction can be blended out over
+/// several render frames instead of applied instantly.
+pub(crate) struct CorrectionState<T: Interpolatable> {
+    displayed: HashMap<Entity, T>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Interpolatable> Default for CorrectionState<T> {
+    fn default() -> Self {
+        Self {
+            displayed: HashMap::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Blends each `RollbackTracked` entity's displayed `T` toward its authoritative value in
+/// `RollbackBuffer::current_world`. Runs in the normal `UPDATE` schedule, entirely outside
+/// `ROLLBACK_UPDATE`, so simulation state is never perturbed by smoothing -- only the
+/// presentation-layer component is adjusted.
+pub(crate) fn correction_smoothing_system<T: Interpolatable>(
+    rollback_buffer: Res<RollbackBuffer>,
+    factor: Res<CorrectionFactor>,
+    mut state: ResMut<CorrectionState<T>>,
+    mut query: Query<(Entity, &mut T), With<RollbackTracked>>,
+) {
+    for (entity, mut displayed) in query.iter_mut() {
+        let target = match rollback_buffer.current_world.get::<T>(entity) {
+            Ok(target) => target.clone(),
+            Err(_) => continue,
+        };
+
+        let last_displayed = state
+            .displayed
+            .entry(entity)
+            .or_insert_with(|| displayed.clone());
+
+        *last_displayed = last_displayed.lerp(&target, factor.0);
+        *displayed = last_displayed.clone();
+    }
+}
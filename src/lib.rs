@@ -3,24 +3,32 @@
 mod res;
 mod query;
 mod commands;
+mod correction;
 
 use bevy::{
-    ecs::{Schedule, Stage, ShouldRun, Archetype},
+    ecs::{Schedule, Stage, ShouldRun, Archetype, ResourceIndex},
     prelude::{
         *,
         stage::UPDATE,
     },
-    reflect::TypeRegistryArc,
-    scene::serde::SceneSerializer,
+    reflect::{TypeRegistryArc, Reflect},
+    scene::{DynamicScene, serde::{SceneSerializer, SceneDeserializer}},
 };
+use serde::de::DeserializeSeed;
 use std::ops::DerefMut;
 use std::collections::hash_map::*;
-use std::any::TypeId;
+use std::collections::HashSet;
+use std::any::{TypeId, Any};
 use std::sync::{Arc, Mutex};
 
-pub use res::{LRes, LResMut};
+pub use res::{LRes, LResMut, LResSet, LNonSend, LNonSendMut};
 pub use query::{LQuery};
 pub use commands::{LogicCommands, LogicCommandsBuilder};
+pub use correction::Interpolatable;
+/// `#[derive(LSystemParam)]`: bundles `LRes`/`LResMut`/`LQuery` fields into a single system param.
+/// Implemented in the sibling `bevy_rollback_archive_macros` proc-macro crate, since a proc-macro
+/// crate can't also export the ordinary items this crate does.
+pub use bevy_rollback_archive_macros::LSystemParam;
 
 pub mod stage{
     pub const ROLLBACK_UPDATE: &str = "rollback_update";
@@ -37,6 +45,8 @@ pub struct RollbackPlugin{
     schedule: Mutex<Option<Schedule>>,
     buffer_size: usize,
     run_criteria: Mutex<Option<Box<dyn System<In = (), Out = ShouldRun>>>>,
+    correction_setup: Mutex<Option<Box<dyn Fn(&mut AppBuilder) + Send + Sync>>>,
+    checksums_enabled: bool,
 }
 
 impl RollbackPlugin{
@@ -45,6 +55,8 @@ impl RollbackPlugin{
             schedule: Mutex::new(Some(schedule)),
             buffer_size,
             run_criteria: Mutex::new(None),
+            correction_setup: Mutex::new(None),
+            checksums_enabled: false,
         }
     }
 
@@ -53,6 +65,8 @@ impl RollbackPlugin{
             schedule: Mutex::new(None),
             buffer_size,
             run_criteria: Mutex::new(None),
+            correction_setup: Mutex::new(None),
+            checksums_enabled: false,
         }
     }
 
@@ -60,13 +74,40 @@ impl RollbackPlugin{
         self.run_criteria = Mutex::new(Some(Box::new(system)));
         self
     }
+
+    /// Enables post-rollback visual correction for `T`: instead of snapping a `RollbackTracked`
+    /// entity's `T` instantly to its resimulated value, it's blended toward it a fraction at a
+    /// time in the normal `UPDATE` schedule: each frame closes `1 / correction_ticks_factor` of the
+    /// remaining gap (factors below `1.0` count as `1.0`), so the gap shrinks geometrically rather
+    /// than closing after a fixed number of frames, and the rate does not depend on how many ticks
+    /// the rollback resimulated. The simulation itself (running in `ROLLBACK_UPDATE`) is never
+    /// touched by this.
+    pub fn with_correction_smoothing<T: correction::Interpolatable>(self, correction_ticks_factor: f32) -> Self{
+        *self.correction_setup.lock().unwrap() = Some(Box::new(move |app: &mut AppBuilder| {
+            app
+                .add_resource(correction::CorrectionFactor(1.0 / correction_ticks_factor.max(1.0)))
+                .add_resource(correction::CorrectionState::<T>::default())
+                .add_system_to_stage(UPDATE, correction::correction_smoothing_system::<T>.system());
+        }));
+        self
+    }
+
+    /// Enables per-frame checksums: every `store_new_world` pass also hashes the frame's reflected
+    /// component state, so peers can exchange checksums for confirmed frames and detect desyncs
+    /// via [`RollbackBuffer::check_remote_checksum`]. Off by default since it adds a hashing pass
+    /// to every stored frame.
+    pub fn with_checksums_enabled(mut self) -> Self{
+        self.checksums_enabled = true;
+        self
+    }
 }
 
 impl Plugin for RollbackPlugin{
     fn build(&self, app: &mut AppBuilder){
-        let rollback_buffer = RollbackBuffer::new(
+        let mut rollback_buffer = RollbackBuffer::new(
             self.buffer_size
         );
+        rollback_buffer.checksums_enabled = self.checksums_enabled;
 
         {
             let mut registry = rollback_buffer.logic_registry.write();
@@ -126,6 +167,10 @@ impl Plugin for RollbackPlugin{
                 stage::ROLLBACK_UPDATE,
                 stage
             );
+
+        if let Some(correction_setup) = self.correction_setup.lock().unwrap().take(){
+            (correction_setup)(app);
+        }
     }
 }
 
@@ -195,13 +240,18 @@ impl RollbackStage{
         let mut rollback_buffer_r = resources
                 .get_mut::<RollbackBuffer>()
                 .expect("Couldn't find RollbackBuffer!");
-                
-        let mut rollback_buffer = rollback_buffer_r   
+
+        let mut rollback_buffer = rollback_buffer_r
             .deref_mut();
 
+        // `store_new_resources` just read each tracked resource's `mutated` flag to decide
+        // whether it needed re-cloning; clear it here so only mutations from *this* tick's
+        // schedule (below) mark it dirty again for the next `run_once`.
+        rollback_buffer.current_resources.clear_trackers();
+
         // Run the schedule
         self.schedule.run_once(&mut rollback_buffer.current_world, &mut rollback_buffer.current_resources);
-        
+
     }
 
     pub fn run_rollback(&mut self, world: &mut World, resources: &mut Resources){
@@ -222,26 +272,41 @@ impl RollbackStage{
                         .get_mut::<RollbackBuffer>()
                         .expect("Couldn't find RollbackBuffer!");
 
-                    let target = rollback_buffer.newest_frame % 
+                    let target = state %
                         rollback_buffer
                             .past_worlds
                             .len();
 
                     rollback_buffer
-                        .current_world = rollback_buffer
-                            .past_worlds
-                            .get_mut(target)
-                            .unwrap()
-                            .take()
-                            .expect("Frame doesn't exist!");
+                        .current_world = match rollback_buffer.snapshot_mode{
+                            // Full snapshots can just be swapped in directly, same as before.
+                            SnapshotMode::Full => rollback_buffer
+                                .past_worlds
+                                .get_mut(target)
+                                .unwrap()
+                                .take()
+                                .map(|snapshot| match snapshot{
+                                    FrameSnapshot::Full(world) => world,
+                                    FrameSnapshot::Delta(_) => unreachable!("SnapshotMode::Full never stores a Delta snapshot"),
+                                })
+                                .expect("Frame doesn't exist!"),
+                            // Delta snapshots are read-only: the baseline and its delta chain may
+                            // still be needed to reconstruct a different target frame before the
+                            // ring buffer naturally overwrites them, so clone/replay instead of
+                            // taking ownership. `reconstruct_frame` wants the true absolute frame
+                            // number (it derives the baseline slot itself), not the ring-buffer
+                            // slot index `target` already reduced above.
+                            SnapshotMode::Delta => reconstruct_frame(&rollback_buffer, state),
+                        };
+
+                    // Cached `LQuery` archetype indices refer to the world just replaced.
+                    rollback_buffer.query_caches.lock().unwrap().clear();
 
+                    // Delta-tracked resources are stored sparsely (only the ones mutated that
+                    // frame), so the restored slot is resolved by walking backward rather than
+                    // taken directly -- see `resolve_resources`.
                     rollback_buffer
-                        .current_resources = rollback_buffer
-                            .past_resources
-                            .get_mut(target)
-                            .unwrap()
-                            .take()
-                            .expect("Frame doesn't exist!");
+                        .current_resources = resolve_resources(&rollback_buffer, state);
 
                     // Setup for catchup
                     *rollback_buffer
@@ -346,8 +411,17 @@ enum RollbackState{
 pub enum RollbackError{
     FrameTimeout,
     ResourceNotFound,
+    /// A `past_frame_change`/`confirm_input` call would have rewound further than
+    /// `RollbackBuffer::max_prediction_ticks` allows.
+    PredictionLimitExceeded,
+    /// A remote peer's checksum for `frame` didn't match the local one -- the simulation has
+    /// diverged. See [`RollbackBuffer::check_remote_checksum`].
+    Desync{ frame: usize },
 }
 
+/// Identifies which remote peer/input stream a predicted or confirmed input belongs to.
+pub type InputSource = u32;
+
 pub trait ResourceRollbackFn = Fn(&mut Resources, &Resources) -> () + Sync + Send;
 
 pub struct RollbackBuffer{
@@ -359,13 +433,52 @@ pub struct RollbackBuffer{
 
     buffered_changes: Arc<Mutex<HashMap<usize, SystemStage>>>,
 
-    past_worlds: Vec<Option<World>>,
-    past_resources: Vec<Option<Resources>>,   
+    past_worlds: Vec<Option<FrameSnapshot>>,
+    past_resources: Vec<Option<Resources>>,
+
+    /// Whether `store_new_world` also computes and stores a checksum alongside each slot. Set via
+    /// `RollbackPlugin::with_checksums_enabled`.
+    pub checksums_enabled: bool,
+    /// Per-slot checksum, parallel to `past_worlds`. `None` until `checksums_enabled` is set and a
+    /// frame has actually been stored into that slot.
+    checksums: Vec<Option<u64>>,
 
     resource_rollback: Vec<Box<dyn ResourceRollbackFn>>,
     resource_override: Vec<Box<dyn ResourceRollbackFn>>,
 
+    /// Delta-tracked resources registered via `track_resource`: cloned into a stored slot only
+    /// when actually mutated since the previous snapshot, instead of unconditionally every frame
+    /// like `resource_rollback`. See `resolve_resources`.
+    resource_deltas: Vec<ResourceDelta>,
+
     pub logic_registry: TypeRegistryArc,
+
+    pub(crate) query_caches: Mutex<HashMap<query::LQueryCacheKey, query::LQueryCache>>,
+
+    /// Hands out the fixed `insertion_index` each `LogicCommandsBuilder` is stamped with, via
+    /// [`RollbackBuffer::next_logic_system_index`]. Must only be drawn from at schedule-definition
+    /// time (e.g. once per system when it's registered with `add_logic_system`), never at system
+    /// run time -- see that method's docs.
+    pub(crate) logic_system_sequence: std::sync::atomic::AtomicUsize,
+
+    pub snapshot_mode: SnapshotMode,
+    /// Last reflected value stored per `(Entity, TypeId)`, used by `SnapshotMode::Delta` to tell
+    /// whether a component needs to be recorded again this frame.
+    delta_shadow_values: HashMap<(Entity, TypeId), Box<dyn Reflect>>,
+    /// Entities known to exist as of the last delta snapshot, used to detect spawns/despawns.
+    delta_known_entities: HashSet<Entity>,
+
+    /// How many ticks in the future a locally-scheduled input is applied, giving remote peers
+    /// time to receive and predict-confirm it before it's actually simulated.
+    pub input_delay_ticks: usize,
+    /// How far back a `past_frame_change`/`confirm_input` call is allowed to rewind before it's
+    /// rejected with `RollbackError::PredictionLimitExceeded` instead of silently rolling back.
+    pub max_prediction_ticks: usize,
+    /// The last frame each `InputSource` has had a confirmed (not just predicted) input for.
+    last_confirmed_frame: Mutex<HashMap<InputSource, usize>>,
+    /// What the local simulation predicted for `(source, frame)`, kept around just long enough to
+    /// compare against the eventual confirmed value.
+    predicted_inputs: Mutex<HashMap<(InputSource, usize), Box<dyn Any + Send + Sync>>>,
 }
 
 impl RollbackBuffer{
@@ -382,16 +495,66 @@ impl RollbackBuffer{
             past_worlds: (0..buffer_size).map(|_| None).collect(),
             past_resources: (0..buffer_size).map(|_| None).collect(),
 
+            checksums_enabled: false,
+            checksums: (0..buffer_size).map(|_| None).collect(),
+
             resource_rollback: Vec::new(),
             resource_override: Vec::new(),
+            resource_deltas: Vec::new(),
 
             logic_registry: TypeRegistryArc::default(),
+
+            query_caches: Mutex::new(HashMap::new()),
+
+            logic_system_sequence: std::sync::atomic::AtomicUsize::new(0),
+
+            snapshot_mode: SnapshotMode::Full,
+            delta_shadow_values: HashMap::new(),
+            delta_known_entities: HashSet::new(),
+
+            input_delay_ticks: 0,
+            max_prediction_ticks: buffer_size,
+            last_confirmed_frame: Mutex::new(HashMap::new()),
+            predicted_inputs: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Sets whether frame snapshots are stored as a full world copy ([`SnapshotMode::Full`], the
+    /// default) or as a `Change` log against the nearest baseline ([`SnapshotMode::Delta`]).
+    pub fn with_snapshot_mode(mut self, mode: SnapshotMode) -> Self{
+        self.snapshot_mode = mode;
+        self
+    }
+
+    /// Sets how many ticks in the future locally-scheduled inputs are applied. See
+    /// [`RollbackBuffer::schedule_local_input`].
+    pub fn with_input_delay(mut self, ticks: usize) -> Self{
+        self.input_delay_ticks = ticks;
+        self
+    }
+
+    /// Sets the bound on how far a `past_frame_change`/`confirm_input` call may rewind before
+    /// it's rejected with `RollbackError::PredictionLimitExceeded`.
+    pub fn with_max_prediction(mut self, ticks: usize) -> Self{
+        self.max_prediction_ticks = ticks;
+        self
+    }
+
     pub fn past_frame_change<S: System<In = (), Out = ()>>(&self, frame: usize, op: S) -> Result<(), RollbackError>{
-        if self.newest_frame - frame >= self.past_worlds.len(){
-            return Err(RollbackError::FrameTimeout);
+        // `frame` can be in the future relative to `newest_frame` (e.g. `schedule_local_input`
+        // scheduling `input_delay_ticks` ahead), so this can't be a plain `usize` subtraction.
+        if frame <= self.newest_frame{
+            if self.newest_frame - frame >= self.past_worlds.len(){
+                return Err(RollbackError::FrameTimeout);
+            }
+            if self.newest_frame - frame > self.max_prediction_ticks{
+                return Err(RollbackError::PredictionLimitExceeded);
+            }
+        } else {
+            // A future frame hasn't been simulated yet: `run_once` picks the change up when it
+            // reaches that frame, so no rollback is queued.
+            self.buffered_changes.lock().unwrap().entry(frame).or_insert_with(SystemStage::parallel).add_system(op);
+            return Ok(());
         }
         match self.buffered_changes.lock().unwrap().entry(frame){
             Entry::Occupied(mut o) => o.get_mut().add_system(op),
@@ -410,32 +573,185 @@ impl RollbackBuffer{
         Ok(())
     }
 
-    pub fn get_logic_commands_builder(&self) -> LogicCommandsBuilder{
-        LogicCommandsBuilder::new(self)
+    /// Schedules a *local* input to apply `input_delay_ticks` in the future instead of this
+    /// tick, giving remote peers time to receive and predict-confirm it before it's simulated.
+    pub fn schedule_local_input<S: System<In = (), Out = ()>>(&self, op: S) -> Result<(), RollbackError>{
+        self.past_frame_change(self.newest_frame + self.input_delay_ticks, op)
+    }
+
+    /// Records what the local simulation predicted for `source` at `frame`, so a later
+    /// `confirm_input` call can tell whether the eventual confirmed value actually differs.
+    pub fn predict_input<T: Send + Sync + 'static>(&self, source: InputSource, frame: usize, predicted: T){
+        self.predicted_inputs.lock().unwrap().insert((source, frame), Box::new(predicted));
+    }
+
+    /// Called when a confirmed input for `source` at `frame` arrives. If it matches what was
+    /// `predict_input`-ed for that `(source, frame)`, no rollback is queued; otherwise `op` is
+    /// queued as a retroactive change at `frame`, exactly like `past_frame_change`.
+    pub fn confirm_input<T, S>(&self, source: InputSource, frame: usize, confirmed: &T, op: S) -> Result<(), RollbackError>
+    where
+        T: PartialEq + 'static,
+        S: System<In = (), Out = ()>,
+    {
+        let predicted_matches = self
+            .predicted_inputs
+            .lock()
+            .unwrap()
+            .remove(&(source, frame))
+            .and_then(|predicted| predicted.downcast::<T>().ok())
+            .map_or(false, |predicted| *predicted == *confirmed);
+
+        self.last_confirmed_frame.lock().unwrap().insert(source, frame);
+
+        if predicted_matches{
+            return Ok(());
+        }
+
+        self.past_frame_change(frame, op)
+    }
+
+    /// The last frame `source` has had a confirmed (not merely predicted) input for, if any.
+    pub fn last_confirmed_frame(&self, source: InputSource) -> Option<usize>{
+        self.last_confirmed_frame.lock().unwrap().get(&source).copied()
+    }
+
+    /// Assigns the next fixed `insertion_index` a [`LogicCommandsBuilder`] can be stamped with.
+    /// Call this exactly once per system, at schedule-definition time (e.g. right before
+    /// `add_logic_system` and captured into that system's closure) -- never from inside the
+    /// system itself. Several independent systems can run in parallel within the same tick, so an
+    /// index drawn at system *run* time races between their threads and can order a replay's
+    /// `LogicCommands::merge` differently than the original simulation did; an index fixed by
+    /// registration order is identical every time the schedule runs, no matter how its systems
+    /// happen to interleave on any given tick.
+    pub fn next_logic_system_index(&self) -> usize{
+        self.logic_system_sequence.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Builds a [`LogicCommandsBuilder`] stamped with `insertion_index`, which must have come from
+    /// [`RollbackBuffer::next_logic_system_index`] at schedule-definition time. See that method's
+    /// docs for why the index can't simply be drawn here instead.
+    pub fn get_logic_commands_builder(&self, insertion_index: usize) -> LogicCommandsBuilder{
+        LogicCommandsBuilder::new(self, insertion_index)
+    }
+
+    /// Serializes `frame`'s `World` to a RON blob via `SceneSerializer`, restricted to components
+    /// registered in `logic_registry` -- the same set `store_new_world` already copies. Useful for
+    /// save-states, replay files, and bringing a joining peer up to date with a full authoritative
+    /// state instead of a delta chain it has no baseline for.
+    ///
+    /// Tracked resources aren't part of the blob: unlike components they're rolled back through
+    /// the type-erased `resource_deltas`/`resource_rollback`/`resource_override` closures rather than through
+    /// `logic_registry`, so there's no reflection data here to serialize them generically.
+    pub fn serialize_frame(&self, frame: usize) -> Result<Vec<u8>, RollbackError>{
+        let world = frame_world(self, frame)?;
+        let scene = DynamicScene::from_world(&world, &self.logic_registry);
+
+        let serializer = SceneSerializer::new(&scene, &self.logic_registry);
+        ron::ser::to_string_pretty(&serializer, ron::ser::PrettyConfig::default())
+            .map(String::into_bytes)
+            .map_err(|_| RollbackError::ResourceNotFound)
+    }
+
+    /// Deserializes a blob produced by `serialize_frame` back into `frame`'s ring-buffer slot, as a
+    /// fresh `SnapshotMode::Full` baseline. Rebuilt entity-by-entity through
+    /// `reflect_component.add_component`, the same path `reflect_clone_world` uses, so the
+    /// resulting slot is byte-identical to one that was stored natively.
+    ///
+    /// Under `SnapshotMode::Delta` this is only byte-identical to a natively stored slot when
+    /// `frame` lands on a forced baseline (`frame % buffer_size == 0`); any other frame naturally
+    /// holds a delta chain, and this always writes a full snapshot instead.
+    pub fn deserialize_into_frame(&mut self, frame: usize, bytes: &[u8]) -> Result<(), RollbackError>{
+        let scene: DynamicScene = {
+            let mut deserializer = ron::de::Deserializer::from_bytes(bytes)
+                .map_err(|_| RollbackError::ResourceNotFound)?;
+            let type_registry = self.logic_registry.read();
+            let scene_deserializer = SceneDeserializer{ type_registry: &type_registry };
+            scene_deserializer
+                .deserialize(&mut deserializer)
+                .map_err(|_| RollbackError::ResourceNotFound)?
+        };
+
+        let mut world = World::new();
+        let type_registry = self.logic_registry.read();
+        for dynamic_entity in scene.entities.iter(){
+            let entity = Entity::new(dynamic_entity.entity);
+            world.get_or_spawn(entity);
+            for component in dynamic_entity.components.iter(){
+                if let Some(registration) = type_registry.get_with_name(component.type_name()){
+                    if let Some(reflect_component) = registration.data::<ReflectComponent>(){
+                        reflect_component.add_component(&mut world, &self.current_resources, entity, component.as_ref());
+                    }
+                }
+            }
+        }
+        drop(type_registry);
+
+        // Deserializing always yields a full world, so this slot holds a full snapshot regardless
+        // of `snapshot_mode`. The `SnapshotMode::Delta` shadow is left untouched, so the next
+        // stored delta is still diffed against the last natively stored frame, not against this
+        // world.
+        let slot = frame % self.past_worlds.len();
+        *self.past_worlds
+            .get_mut(slot)
+            .ok_or(RollbackError::FrameTimeout)? = Some(FrameSnapshot::Full(world));
+
+        Ok(())
+    }
+
+    /// The checksum stored for `frame`, if `checksums_enabled` was set when that frame was stored.
+    pub fn frame_checksum(&self, frame: usize) -> Result<u64, RollbackError>{
+        if frame > self.newest_frame || self.newest_frame - frame >= self.checksums.len(){
+            return Err(RollbackError::FrameTimeout);
+        }
+        let slot = frame % self.checksums.len();
+        self.checksums[slot].ok_or(RollbackError::ResourceNotFound)
+    }
+
+    /// Compares `remote_checksum` (received from a peer for the same confirmed `frame`) against
+    /// the local checksum for that frame, returning `RollbackError::Desync` on mismatch.
+    pub fn check_remote_checksum(&self, frame: usize, remote_checksum: u64) -> Result<(), RollbackError>{
+        if self.frame_checksum(frame)? == remote_checksum{
+            Ok(())
+        } else {
+            Err(RollbackError::Desync{ frame })
+        }
     }
 }
 
 pub struct RollbackTracked;
 
-fn store_new_world(resources: &mut Resources, state: usize){
-    let mut rollback_buffer_r = resources
-                .get_mut::<RollbackBuffer>()
-                .expect("Couldn't find RollbackBuffer!");
-                
-        let mut rollback_buffer = rollback_buffer_r   
-            .deref_mut();
-        
-    let mut world = &mut rollback_buffer
-        .current_world;
-    
-    let resources = &rollback_buffer
-        .current_resources;
-
-    let mut new_world = World::new();
+/// Whether `RollbackBuffer` stores each ring-buffer slot as a full copy of the world or as a
+/// `Change` log against the nearest baseline.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SnapshotMode{
+    /// Reflect and reconstruct the entire world into a new `World` every frame. Simple, and the
+    /// cost is proportional to total entity count rather than how much actually changed.
+    Full,
+    /// Only record the components that changed since the previous snapshot. A baseline
+    /// (`SnapshotMode::Full`-style full copy) is forced whenever the ring buffer wraps, so a
+    /// slot's delta chain never depends on a baseline that has since been overwritten.
+    Delta,
+}
 
+/// A single recorded mutation between two consecutive `SnapshotMode::Delta` snapshots.
+pub(crate) enum Change{
+    Component{ entity: Entity, type_id: TypeId, value: Box<dyn Reflect> },
+    Spawn(Entity),
+    Despawn(Entity),
+}
 
-    let type_registry = rollback_buffer.logic_registry.read();
+/// One ring-buffer slot: either a full baseline world or a delta against the previous slot.
+pub(crate) enum FrameSnapshot{
+    Full(World),
+    Delta(Vec<Change>),
+}
 
+/// Reflects `world`'s archetypes into a brand-new, independent `World`. This is the full-copy
+/// path used both by `SnapshotMode::Full` every frame and by `SnapshotMode::Delta` whenever a new
+/// baseline is forced.
+fn reflect_clone_world(world: &World, resources: &Resources, logic_registry: &TypeRegistryArc) -> World{
+    let mut new_world = World::new();
+    let type_registry = logic_registry.read();
 
     for archetype in world.archetypes(){
         for (index, entity) in archetype.iter_entities().enumerate() {
@@ -466,15 +782,366 @@ fn store_new_world(resources: &mut Resources, state: usize){
         }
     }
 
+    new_world
+}
+
+/// A stable, order-independent checksum over `world`'s reflected component values -- the same set
+/// `reflect_clone_world` would copy -- used to detect desyncs between peers that should be
+/// bit-identical after the same logical frame. Per-component digests are XORed into a per-entity
+/// digest (order-independent across a single entity's components), and per-entity digests are
+/// combined with a wrapping add (order-independent across entities), so the result doesn't depend
+/// on archetype or entity iteration order, only on which entities/components/values exist.
+fn checksum_world(world: &World, logic_registry: &TypeRegistryArc) -> u64{
+    use std::hash::{Hash, Hasher};
+    use std::collections::hash_map::DefaultHasher;
+
+    let type_registry = logic_registry.read();
+    let mut checksum: u64 = 0;
+
+    for archetype in world.archetypes(){
+        for (index, entity) in archetype.iter_entities().enumerate(){
+            let mut entity_digest = {
+                let mut hasher = DefaultHasher::new();
+                entity.hash(&mut hasher);
+                hasher.finish()
+            };
+
+            for type_info in archetype.types(){
+                if let Some(registration) = type_registry.get(type_info.id()){
+                    if let Some(reflect_component) = registration.data::<ReflectComponent>(){
+                        let comp = unsafe{ reflect_component.reflect_component(archetype, index) };
+                        let mut hasher = DefaultHasher::new();
+                        type_info.id().hash(&mut hasher);
+                        hash_reflected_value(comp, &mut hasher);
+                        entity_digest ^= hasher.finish();
+                    }
+                }
+            }
+
+            checksum = checksum.wrapping_add(entity_digest);
+        }
+    }
+
+    checksum
+}
+
+/// Hashes a reflected value's actual data into `hasher`, recursing into compound types instead of
+/// deferring to `Reflect::reflect_hash`. `reflect_hash` is what bevy_reflect's blanket
+/// `impl_reflect_value!` gives numeric leaf types like `f32`/`f64` for free, but floats aren't
+/// `Hash` so those impls just return `None` -- `checksum_world` needs every field to actually
+/// contribute, since desyncs in rollback games overwhelmingly show up in position/velocity floats.
+fn hash_reflected_value(value: &dyn Reflect, hasher: &mut impl std::hash::Hasher){
+    use bevy::reflect::ReflectRef;
+    use std::hash::Hash;
+
+    match value.reflect_ref(){
+        ReflectRef::Struct(s) => {
+            for i in 0..s.field_len(){
+                if let Some(field) = s.field_at(i){
+                    hash_reflected_value(field, hasher);
+                }
+            }
+        },
+        ReflectRef::TupleStruct(s) => {
+            for i in 0..s.field_len(){
+                if let Some(field) = s.field(i){
+                    hash_reflected_value(field, hasher);
+                }
+            }
+        },
+        ReflectRef::Tuple(t) => {
+            for i in 0..t.field_len(){
+                if let Some(field) = t.field(i){
+                    hash_reflected_value(field, hasher);
+                }
+            }
+        },
+        ReflectRef::List(l) => {
+            for item in l.iter(){
+                hash_reflected_value(item, hasher);
+            }
+        },
+        ReflectRef::Map(m) => {
+            for (key, field) in m.iter(){
+                hash_reflected_value(key, hasher);
+                hash_reflected_value(field, hasher);
+            }
+        },
+        ReflectRef::Value(v) => {
+            // Bit-hash the numeric leaf types whose `reflect_hash` is a guaranteed `None` instead
+            // of falling back to it; everything else (bools, strings, enums that already
+            // implement `Hash`, ...) still goes through `reflect_hash`.
+            if let Some(v) = v.downcast_ref::<f32>(){
+                v.to_bits().hash(hasher);
+            } else if let Some(v) = v.downcast_ref::<f64>(){
+                v.to_bits().hash(hasher);
+            } else {
+                v.reflect_hash().unwrap_or(0).hash(hasher);
+            }
+        },
+    }
+}
+
+/// Rebuilds the shadow of last-seen reflected values from scratch, used right after a new
+/// baseline is stored so the next delta is diffed against exactly what the baseline holds.
+fn reset_delta_shadow(
+    world: &World,
+    logic_registry: &TypeRegistryArc,
+    shadow: &mut HashMap<(Entity, TypeId), Box<dyn Reflect>>,
+    known: &mut HashSet<Entity>,
+){
+    shadow.clear();
+    known.clear();
+    let type_registry = logic_registry.read();
+
+    for archetype in world.archetypes(){
+        for (index, entity) in archetype.iter_entities().enumerate(){
+            known.insert(*entity);
+            for type_info in archetype.types(){
+                if let Some(registration) = type_registry.get(type_info.id()){
+                    if let Some(reflect_component) = registration.data::<ReflectComponent>(){
+                        let comp = unsafe{ reflect_component.reflect_component(archetype, index) };
+                        shadow.insert((*entity, type_info.id()), comp.clone_value());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Diffs `world` against `shadow`/`known` (the last snapshot's reflected values and entity set),
+/// updating both in place and returning a `Change` log of exactly what's different this frame.
+fn diff_against_delta_shadow(
+    world: &World,
+    logic_registry: &TypeRegistryArc,
+    shadow: &mut HashMap<(Entity, TypeId), Box<dyn Reflect>>,
+    known: &mut HashSet<Entity>,
+) -> Vec<Change>{
+    let mut changes = Vec::new();
+    let mut seen = HashSet::new();
+    let type_registry = logic_registry.read();
+
+    for archetype in world.archetypes(){
+        for (index, entity) in archetype.iter_entities().enumerate(){
+            let entity = *entity;
+            seen.insert(entity);
+            if known.insert(entity){
+                changes.push(Change::Spawn(entity));
+            }
+
+            for type_info in archetype.types(){
+                if let Some(registration) = type_registry.get(type_info.id()){
+                    if let Some(reflect_component) = registration.data::<ReflectComponent>(){
+                        let comp = unsafe{ reflect_component.reflect_component(archetype, index) };
+                        let key = (entity, type_info.id());
+                        let unchanged = shadow
+                            .get(&key)
+                            .map(|previous| comp.reflect_partial_eq(previous.as_ref()) == Some(true))
+                            .unwrap_or(false);
+
+                        if !unchanged{
+                            shadow.insert(key, comp.clone_value());
+                            changes.push(Change::Component{
+                                entity,
+                                type_id: type_info.id(),
+                                value: comp.clone_value(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Anything that was known before but didn't show up in this frame's archetypes was despawned.
+    let despawned: Vec<Entity> = known.difference(&seen).copied().collect();
+    for entity in despawned{
+        known.remove(&entity);
+        shadow.retain(|(shadow_entity, _), _| *shadow_entity != entity);
+        changes.push(Change::Despawn(entity));
+    }
+
+    changes
+}
+
+/// Applies a single recorded `Change` to `world` during delta-chain replay.
+fn apply_change(world: &mut World, resources: &Resources, logic_registry: &TypeRegistryArc, change: &Change){
+    match change{
+        Change::Spawn(entity) => {
+            world.get_or_spawn(*entity);
+        }
+        Change::Despawn(entity) => {
+            let _ = world.despawn(*entity);
+        }
+        Change::Component{ entity, type_id, value } => {
+            let type_registry = logic_registry.read();
+            if let Some(registration) = type_registry.get(*type_id){
+                if let Some(reflect_component) = registration.data::<ReflectComponent>(){
+                    reflect_component.add_component(world, resources, *entity, value.as_ref());
+                }
+            }
+        }
+    }
+}
+
+/// Reconstructs the world as of `frame` by cloning the nearest baseline and replaying the delta
+/// chain forward up to `frame`, rather than swapping in a fully-materialized world. Baselines and
+/// deltas are only read here, never taken, since the same baseline can back several different
+/// rollback targets before the ring buffer overwrites it.
+fn reconstruct_frame(rollback_buffer: &RollbackBuffer, frame: usize) -> World{
+    let buffer_size = rollback_buffer.past_worlds.len();
+    // Baselines are forced at every multiple of `buffer_size`, so the nearest one is always
+    // within the live ring-buffer window.
+    let baseline_frame = (frame / buffer_size) * buffer_size;
+
+    let baseline_slot = baseline_frame % buffer_size;
+    let mut world = match rollback_buffer.past_worlds[baseline_slot].as_ref().expect("Frame doesn't exist!"){
+        FrameSnapshot::Full(world) => reflect_clone_world(world, &rollback_buffer.current_resources, &rollback_buffer.logic_registry),
+        FrameSnapshot::Delta(_) => panic!("Baseline slot should always hold a SnapshotMode::Full snapshot!"),
+    };
+
+    for replay_frame in (baseline_frame + 1)..=frame{
+        let slot = replay_frame % buffer_size;
+        match rollback_buffer.past_worlds[slot].as_ref().expect("Frame doesn't exist!"){
+            FrameSnapshot::Full(full_world) => world = reflect_clone_world(full_world, &rollback_buffer.current_resources, &rollback_buffer.logic_registry),
+            FrameSnapshot::Delta(changes) => {
+                for change in changes{
+                    apply_change(&mut world, &rollback_buffer.current_resources, &rollback_buffer.logic_registry, change);
+                }
+            }
+        }
+    }
+
+    world
+}
+
+/// Materializes `frame`'s `World`, whether its slot holds a `SnapshotMode::Full` copy or a
+/// `SnapshotMode::Delta` chain. Shared by rollback itself and by `serialize_frame`, neither of
+/// which should care which mode produced the slot.
+fn frame_world(rollback_buffer: &RollbackBuffer, frame: usize) -> Result<World, RollbackError>{
+    if frame > rollback_buffer.newest_frame || rollback_buffer.newest_frame - frame >= rollback_buffer.past_worlds.len(){
+        return Err(RollbackError::FrameTimeout);
+    }
+
+    let slot = frame % rollback_buffer.past_worlds.len();
+    Ok(match rollback_buffer.past_worlds[slot].as_ref().expect("Frame doesn't exist!"){
+        FrameSnapshot::Full(world) => reflect_clone_world(world, &rollback_buffer.current_resources, &rollback_buffer.logic_registry),
+        FrameSnapshot::Delta(_) => reconstruct_frame(rollback_buffer, frame),
+    })
+}
+
+fn store_new_world(resources: &mut Resources, state: usize){
+    let mut rollback_buffer_r = resources
+                .get_mut::<RollbackBuffer>()
+                .expect("Couldn't find RollbackBuffer!");
+
+        let mut rollback_buffer = rollback_buffer_r
+            .deref_mut();
+
     let buffer_pos = state %
         rollback_buffer
-            .past_resources
+            .past_worlds
             .len();
+    // A fresh baseline is forced every time the ring buffer wraps back to slot 0, so a delta
+    // chain never needs to reach past the start of the live buffer window.
+    let force_baseline = buffer_pos == 0;
+
+    let snapshot = match rollback_buffer.snapshot_mode{
+        SnapshotMode::Full => FrameSnapshot::Full(reflect_clone_world(
+            &rollback_buffer.current_world,
+            &rollback_buffer.current_resources,
+            &rollback_buffer.logic_registry,
+        )),
+        SnapshotMode::Delta if force_baseline => {
+            let world = reflect_clone_world(
+                &rollback_buffer.current_world,
+                &rollback_buffer.current_resources,
+                &rollback_buffer.logic_registry,
+            );
+            let current_world = &rollback_buffer.current_world;
+            let logic_registry = &rollback_buffer.logic_registry;
+            reset_delta_shadow(current_world, logic_registry, &mut rollback_buffer.delta_shadow_values, &mut rollback_buffer.delta_known_entities);
+            FrameSnapshot::Full(world)
+        }
+        SnapshotMode::Delta => {
+            let current_world = &rollback_buffer.current_world;
+            let logic_registry = &rollback_buffer.logic_registry;
+            FrameSnapshot::Delta(diff_against_delta_shadow(
+                current_world,
+                logic_registry,
+                &mut rollback_buffer.delta_shadow_values,
+                &mut rollback_buffer.delta_known_entities,
+            ))
+        }
+    };
+
+    if rollback_buffer.checksums_enabled{
+        let checksum = checksum_world(&rollback_buffer.current_world, &rollback_buffer.logic_registry);
+        *rollback_buffer
+            .checksums
+            .get_mut(buffer_pos)
+            .expect("RollbackBuffer Index is out of bounds!") = Some(checksum);
+    }
 
     *rollback_buffer
         .past_worlds
         .get_mut(buffer_pos)
-        .expect("RollbackBuffer Index is out of bounds!") = Some(new_world);      
+        .expect("RollbackBuffer Index is out of bounds!") = Some(snapshot);
+}
+
+/// A `track_resource`-registered resource's delta-snapshot machinery: a clone function (same shape
+/// as `ResourceRollbackFn`), a probe for whether `current_resources`'s copy has been mutated since
+/// the last snapshot, and a probe for whether a given stored slot happens to contain this resource
+/// at all (used by `resolve_resources`'s backward walk).
+pub(crate) struct ResourceDelta{
+    clone_fn: Box<dyn ResourceRollbackFn>,
+    mutated_fn: Box<dyn Fn(&Resources) -> bool + Send + Sync>,
+    present_fn: Box<dyn Fn(&Resources) -> bool + Send + Sync>,
+}
+
+/// Materializes the full `Resources` as of `frame` by walking backward through the ring buffer's
+/// sparse resource slots until every delta-tracked resource has been found -- the same idea
+/// `reconstruct_frame` uses for worlds. A full baseline (every delta-tracked resource present) is
+/// forced every time the buffer wraps to slot 0 (see `store_new_resources`), so the walk is always
+/// bounded by the live window.
+///
+/// `resource_rollback` entries (everything registered via `override_resource`) are always present
+/// in every slot, so they're only ever read from `frame`'s own slot.
+fn resolve_resources(rollback_buffer: &RollbackBuffer, frame: usize) -> Resources{
+    let buffer_size = rollback_buffer.past_resources.len();
+    let mut resolved = Resources::default();
+    let mut pending: Vec<usize> = (0..rollback_buffer.resource_deltas.len()).collect();
+
+    let mut walk_frame = frame;
+    let mut first = true;
+    loop{
+        let slot = walk_frame % buffer_size;
+        let snapshot = rollback_buffer.past_resources[slot].as_ref().expect("Frame doesn't exist!");
+
+        if first{
+            for resource_rollback_fn in rollback_buffer.resource_rollback.iter(){
+                (resource_rollback_fn)(&mut resolved, snapshot);
+            }
+            first = false;
+        }
+
+        pending.retain(|&i| {
+            let delta = &rollback_buffer.resource_deltas[i];
+            if (delta.present_fn)(snapshot){
+                (delta.clone_fn)(&mut resolved, snapshot);
+                false
+            } else {
+                true
+            }
+        });
+
+        if pending.is_empty() || slot == 0{
+            break;
+        }
+        walk_frame -= 1;
+    }
+
+    resolved
 }
 
 fn store_new_resources(resources: &mut Resources, state: usize){
@@ -484,22 +1151,35 @@ fn store_new_resources(resources: &mut Resources, state: usize){
 
     let mut new_resources = Resources::default();
 
-    for resource_rollback_fn in rollback_buffer.resource_rollback.iter(){
-        (resource_rollback_fn)(&mut new_resources, &rollback_buffer.current_resources);
-    }
-
-    // Since new_resources is an exact copy of the current_resources, we can just store new_resources
-    // This should also drop the old resources question mark?
-
     let buffer_pos = state %
         rollback_buffer
             .past_resources
             .len();
+    // Mirrors `store_new_world`'s forced baseline: every delta-tracked resource is written out in
+    // full whenever the ring buffer wraps back to slot 0, so `resolve_resources`'s backward walk
+    // is always bounded by the live window.
+    let force_baseline = buffer_pos == 0;
+
+    for resource_rollback_fn in rollback_buffer.resource_rollback.iter(){
+        (resource_rollback_fn)(&mut new_resources, &rollback_buffer.current_resources);
+    }
+
+    // Delta-tracked resources: only clone the ones actually written to since the last snapshot
+    // (or unconditionally at a forced baseline). Note the invariant this relies on: `mutated_fn`'s
+    // underlying flag must be cleared exactly once per frame boundary, by the `clear_trackers`
+    // call in `RollbackStage::run_once` (the same flag `LResMut::deref_mut` sets) -- clearing it
+    // twice in one frame would let an earlier
+    // mutation within that frame go unreported here.
+    for delta in rollback_buffer.resource_deltas.iter(){
+        if force_baseline || (delta.mutated_fn)(&rollback_buffer.current_resources){
+            (delta.clone_fn)(&mut new_resources, &rollback_buffer.current_resources);
+        }
+    }
 
     *rollback_buffer
         .past_resources
         .get_mut(buffer_pos)
-        .expect("RollbackBuffer Index is out of bounds!") = Some(new_resources);  
+        .expect("RollbackBuffer Index is out of bounds!") = Some(new_resources);
 }
 
 pub trait ResourceTracker{
@@ -515,12 +1195,17 @@ impl ResourceTracker for AppBuilder{
             rollback_buffer.current_resources.insert(resource);
 
             rollback_buffer
-                .resource_rollback
-                .push(
-                    Box::new(|dest_res: &mut Resources, res: &Resources|{
+                .resource_deltas
+                .push(ResourceDelta{
+                    clone_fn: Box::new(|dest_res: &mut Resources, res: &Resources|{
                         dest_res.insert(res.get_cloned::<R>().unwrap());
-                    })
-            );
+                    }),
+                    mutated_fn: Box::new(|res: &Resources| unsafe{
+                        let (_, _added, mutated) = res.get_unsafe_ref_with_added_and_mutated::<R>(ResourceIndex::Global);
+                        *mutated.as_ptr()
+                    }),
+                    present_fn: Box::new(|res: &Resources| res.get::<R>().is_some()),
+                });
         }
         self
     }
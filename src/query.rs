@@ -1,24 +1,112 @@
 use crate::res::L;
 use crate::RollbackBuffer;
-use bevy::ecs::{WorldQuery, QueryFilter, World, TypeAccess, ArchetypeComponent,
+use bevy::ecs::{WorldQuery, QueryFilter, World, TypeAccess, ArchetypeComponent, Archetype,
     Batch, BatchedIter, QueryError, Entity, Component, Mut, ReadOnlyFetch,
     QueryIter, Fetch, ComponentError, SystemParam, FetchSystemParam, SystemState,
-    Resources, QueryAccess, ResourceIndex};
-use bevy::tasks::{ParallelIterator};
+    Resources, QueryAccess, ResourceIndex, With, Without, Changed, Or};
+use bevy::tasks::{ParallelIterator, TaskPool};
 use std::marker::PhantomData;
 use std::any::TypeId;
 use std::ops::Deref;
 use std::ptr::NonNull;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Marker for `QueryFilter`s that can never fetch component data mutably. `LQuery`'s filter
+/// parameter `F` is bounded on this trait instead of plain `QueryFilter` so that a filter can
+/// never alias a mutable `Q` fetch through the back door: `get_component_mut`/`set`/`iter_mut`
+/// are only sound if every live filter on the same query is guaranteed read-only.
+///
+/// This is implemented for the built-in filters (`With`, `Without`, `Changed`, `()`, and `Or` of
+/// read-only filters); it is not implemented for anything that fetches mutably, so passing such a
+/// type as `F` is a compile error rather than latent UB.
+pub trait ReadOnlyQueryFilter: QueryFilter {}
+
+impl ReadOnlyQueryFilter for () {}
+impl<T: Component> ReadOnlyQueryFilter for With<T> {}
+impl<T: Component> ReadOnlyQueryFilter for Without<T> {}
+impl<T: Component> ReadOnlyQueryFilter for Changed<T> {}
+
+macro_rules! impl_read_only_query_filter_tuple {
+    ($($name: ident),*) => {
+        impl<$($name: ReadOnlyQueryFilter),*> ReadOnlyQueryFilter for Or<($($name,)*)> {}
+    };
+}
+
+impl_read_only_query_filter_tuple!(A);
+impl_read_only_query_filter_tuple!(A, B);
+impl_read_only_query_filter_tuple!(A, B, C);
+impl_read_only_query_filter_tuple!(A, B, C, D);
+
+/// Key identifying a cached [`LQueryCache`] entry: the concrete `(Q, F)` shape of an `LQuery`.
+/// Matched archetypes only depend on this shape and the world's archetypes, not on which system
+/// runs the query, so systems sharing a query shape share one cache entry.
+pub(crate) type LQueryCacheKey = (TypeId, TypeId);
+
+/// Caches which archetype indices in a `RollbackBuffer`'s `current_world` currently match a given
+/// `(Q, F)` query shape. `World` only ever appends archetypes, it never removes or reorders them,
+/// so "has anything changed since last time" is just comparing archetype counts, and a growth in
+/// that count means only the newly appended archetypes need to be tested.
+///
+/// Consumed by [`matched_entities`], which `LQuery::iter`/`iter_mut`/`for_each`/`for_each_mut` walk
+/// instead of going through bevy's own `query_unchecked` -- so the archetype-matching done here
+/// only has to happen once per new archetype, not once per call to any of those.
+#[derive(Default)]
+pub(crate) struct LQueryCache {
+    archetype_count: usize,
+    matched: Vec<usize>,
+}
+
+/// Looks up (or creates) the `(Q, F)` cache entry in `caches`, grows it to cover any archetypes
+/// added to `world` since the last refresh, and returns the up-to-date list of matched archetype
+/// indices.
+pub(crate) fn refresh_query_cache<Q: WorldQuery, F: ReadOnlyQueryFilter>(
+    caches: &Mutex<HashMap<LQueryCacheKey, LQueryCache>>,
+    world: &World,
+) -> Vec<usize> {
+    let key = (TypeId::of::<Q>(), TypeId::of::<F>());
+    let mut caches = caches.lock().unwrap();
+    let cache = caches.entry(key).or_insert_with(LQueryCache::default);
+
+    let archetypes: Vec<&Archetype> = world.archetypes().collect();
+    if archetypes.len() > cache.archetype_count {
+        for (index, archetype) in archetypes.iter().enumerate().skip(cache.archetype_count) {
+            if Q::Fetch::matches_archetype(archetype) && F::Fetch::matches_archetype(archetype) {
+                cache.matched.push(index);
+            }
+        }
+        cache.archetype_count = archetypes.len();
+    }
+
+    cache.matched.clone()
+}
+
+/// Flattens every entity belonging to `matched_archetypes` into one list, in archetype order. The
+/// archetype-matching work that would otherwise repeat on every call (`Q::Fetch::matches_archetype`
+/// / `F::Fetch::matches_archetype` against every archetype in `world`) was already done once by
+/// `refresh_query_cache`, so this only ever walks the archetypes already known to match.
+fn matched_entities(world: &World, matched_archetypes: &[usize]) -> Vec<Entity> {
+    let archetypes: Vec<&Archetype> = world.archetypes().collect();
+    let mut entities = Vec::new();
+    for &index in matched_archetypes {
+        if let Some(archetype) = archetypes.get(index) {
+            entities.extend(archetype.iter_entities());
+        }
+    }
+    entities
+}
 
 /// Provides scoped access to a World according to a given [HecsQuery]
-pub struct LQuery<'a, Q: WorldQuery, F: QueryFilter = ()> {
+pub struct LQuery<'a, Q: WorldQuery, F: ReadOnlyQueryFilter = ()> {
     pub(crate) world: NonNull<RollbackBuffer>,
     pub(crate) component_access: &'a TypeAccess<ArchetypeComponent>,
+    pub(crate) matched_archetypes: Vec<usize>,
     _marker: PhantomData<(Q, F)>,
 }
 
 
-impl<'a, Q: WorldQuery, F: QueryFilter> LQuery<'a, Q, F> {
+impl<'a, Q: WorldQuery, F: ReadOnlyQueryFilter> LQuery<'a, Q, F> {
     /// # Safety
     /// This will create a Query that could violate memory safety rules. Make sure that this is only called in
     /// ways that ensure the Queries have unique mutable access.
@@ -26,29 +114,44 @@ impl<'a, Q: WorldQuery, F: QueryFilter> LQuery<'a, Q, F> {
     pub(crate) unsafe fn new(
         world: NonNull<RollbackBuffer>,
         component_access: &'a TypeAccess<ArchetypeComponent>,
+        matched_archetypes: Vec<usize>,
     ) -> Self {
         Self {
             world,
             component_access,
+            matched_archetypes,
             _marker: PhantomData::default(),
         }
     }
 
-    /// Iterates over the query results. This can only be called for read-only queries
+    /// Iterates over the query results. This can only be called for read-only queries.
+    ///
+    /// Walks only `matched_archetypes` (via [`matched_entities`]) instead of bevy's own
+    /// `query_unchecked`, which would re-run `Q::Fetch::matches_archetype`/
+    /// `F::Fetch::matches_archetype` against every archetype in `current_world` on every call.
     #[inline]
-    pub fn iter(&self) -> QueryIter<'_, Q, F>
+    pub fn iter(&self) -> QueryManyIter<'_, Q, F, std::vec::IntoIter<Entity>>
     where
         Q::Fetch: ReadOnlyFetch,
     {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
-        unsafe { self.world.as_ref().current_world.query_unchecked() }
+        unsafe {
+            let entities = matched_entities(&self.world.as_ref().current_world, &self.matched_archetypes);
+            QueryManyIter::new(self.world, entities.into_iter())
+        }
     }
 
-    /// Iterates over the query results
+    /// Iterates over the query results.
+    ///
+    /// Walks only `matched_archetypes` the same way [`LQuery::iter`] does, instead of bevy's own
+    /// `query_unchecked`.
     #[inline]
-    pub fn iter_mut(&mut self) -> QueryIter<'_, Q, F> {
+    pub fn iter_mut(&mut self) -> CachedQueryIterMut<'_, Q, F> {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
-        unsafe { self.world.as_ref().current_world.query_unchecked() }
+        unsafe {
+            let entities = matched_entities(&self.world.as_ref().current_world, &self.matched_archetypes);
+            CachedQueryIterMut::new(self.world, entities)
+        }
     }
 
     /// Iterates over the query results
@@ -75,7 +178,9 @@ impl<'a, Q: WorldQuery, F: QueryFilter> LQuery<'a, Q, F> {
         unsafe { ParIter::new(self.world.as_ref().current_world.query_batched_unchecked(batch_size)) }
     }
 
-    /// Gets the query result for the given `entity`
+    /// Gets the query result for the given `entity`. Resolves `entity`'s archetype directly via
+    /// `query_one_unchecked` rather than scanning archetypes at all, so unlike `iter`/`for_each`
+    /// this was never the archetype-rescan hot path `matched_archetypes` exists to avoid.
     #[inline]
     pub fn get(&self, entity: Entity) -> Result<<Q::Fetch as Fetch>::Item, QueryError>
     where
@@ -212,22 +317,205 @@ impl<'a, Q: WorldQuery, F: QueryFilter> LQuery<'a, Q, F> {
         *current = component;
         Ok(())
     }
+
+    /// Number of archetypes in `current_world` that match this query, from the cached scan done
+    /// in `FetchLQuery::get_param`. Useful as a quick hint when deciding a `par_for_each_mut`
+    /// batch size.
+    #[inline]
+    pub fn matched_archetype_count(&self) -> usize {
+        self.matched_archetypes.len()
+    }
+
+    /// Calls `func` on each item matching this query directly, without handing out an `Iterator`.
+    /// Only available for read-only queries. Walks `matched_archetypes` (via [`matched_entities`])
+    /// and resolves each entity with `query_one_unchecked` in a plain loop -- the same shape as
+    /// [`LQuery::many_for_each_mut`] -- instead of driving [`LQuery::iter`]'s `Iterator` impl, so
+    /// there's no per-element `Iterator::next` branching standing between `func` and each match.
+    #[inline]
+    pub fn for_each(&self, mut func: impl FnMut(<Q::Fetch as Fetch>::Item))
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
+        unsafe {
+            let entities = matched_entities(&self.world.as_ref().current_world, &self.matched_archetypes);
+            for entity in entities {
+                if let Ok(item) = self.world.as_ref().current_world.query_one_unchecked::<Q, F>(entity) {
+                    func(item);
+                }
+            }
+        }
+    }
+
+    /// Mutable counterpart of [`LQuery::for_each`]: calls `func` on each item matching this query
+    /// directly, the same way `for_each` does.
+    #[inline]
+    pub fn for_each_mut(&mut self, mut func: impl FnMut(<Q::Fetch as Fetch>::Item)) {
+        // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
+        unsafe {
+            let entities = matched_entities(&self.world.as_ref().current_world, &self.matched_archetypes);
+            for entity in entities {
+                if let Ok(item) = self.world.as_ref().current_world.query_one_unchecked::<Q, F>(entity) {
+                    func(item);
+                }
+            }
+        }
+    }
+
+    /// Parallel counterpart of [`LQuery::for_each_mut`], fanning work for each batch of
+    /// `batch_size` matches across `pool` instead of driving a [`ParIter`] by hand.
+    #[inline]
+    pub fn par_for_each_mut(
+        &mut self,
+        pool: &TaskPool,
+        batch_size: usize,
+        func: impl Fn(<Q::Fetch as Fetch>::Item) + Send + Sync + Clone,
+    ) {
+        self.par_iter_mut(batch_size).for_each(pool, func);
+    }
+
+    /// Iterates over `entities` in order, yielding query results for the entities that match
+    /// `Q`/`F` and silently skipping the rest. Unlike [`LQuery::iter`], results are produced in
+    /// exactly the order `entities` is walked rather than archetype order, which is what rollback
+    /// logic needs when determinism depends on a fixed, caller-supplied entity order (e.g. the
+    /// entities owned by each networked peer).
+    #[inline]
+    pub fn iter_many<I>(&self, entities: I) -> QueryManyIter<'_, Q, F, I::IntoIter>
+    where
+        Q::Fetch: ReadOnlyFetch,
+        I: IntoIterator,
+        I::Item: Borrow<Entity>,
+    {
+        // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
+        unsafe { QueryManyIter::new(self.world, entities.into_iter()) }
+    }
+
+    /// Runs `func` for every entity in `entities`, in order, skipping entities that don't match
+    /// `Q`/`F`. This is the mutable counterpart of [`LQuery::iter_many`]: a plain `Iterator` over
+    /// `Mut` items would let the same entity alias itself if it appears twice in the list, so
+    /// instead each item only lives for the duration of a single call to `func`.
+    pub fn many_for_each_mut<I>(&mut self, entities: I, mut func: impl FnMut(<Q::Fetch as Fetch>::Item))
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Entity>,
+    {
+        for entity in entities {
+            let entity = *entity.borrow();
+            // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict.
+            // `func` is called with at most one live item at a time, so a repeated entity can never alias.
+            unsafe {
+                if let Ok(item) = self.world.as_ref().current_world.query_one_unchecked::<Q, F>(entity) {
+                    func(item);
+                }
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`LQuery::iter_many`] that yields query results for a caller-supplied
+/// list of entities, in the order given, skipping entities that don't match `Q`/`F`.
+pub struct QueryManyIter<'w, Q: WorldQuery, F: ReadOnlyQueryFilter, I: Iterator>
+where
+    I::Item: Borrow<Entity>,
+{
+    world: NonNull<RollbackBuffer>,
+    entities: I,
+    _marker: PhantomData<(&'w (), Q, F)>,
+}
+
+impl<'w, Q: WorldQuery, F: ReadOnlyQueryFilter, I: Iterator> QueryManyIter<'w, Q, F, I>
+where
+    I::Item: Borrow<Entity>,
+{
+    /// # Safety
+    /// `world` must be valid for `'w` and callers must ensure this iterator does not alias with
+    /// other mutable access to the same components.
+    unsafe fn new(world: NonNull<RollbackBuffer>, entities: I) -> Self {
+        Self {
+            world,
+            entities,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'w, Q: WorldQuery, F: ReadOnlyQueryFilter, I: Iterator> Iterator for QueryManyIter<'w, Q, F, I>
+where
+    Q::Fetch: ReadOnlyFetch,
+    I::Item: Borrow<Entity>,
+{
+    type Item = <Q::Fetch as Fetch<'w>>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entity = *self.entities.next()?.borrow();
+            // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
+            unsafe {
+                if let Ok(item) = self.world.as_ref().current_world.query_one_unchecked::<Q, F>(entity) {
+                    return Some(item);
+                }
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`LQuery::iter_mut`] over a fixed list of entities already known (via
+/// [`matched_entities`]) to belong to one of this query's matched archetypes. Unlike
+/// [`QueryManyIter`], this does not require `Q::Fetch: ReadOnlyFetch`: `matched_entities` flattens
+/// entities archetype-by-archetype, and an entity belongs to exactly one archetype at a time, so
+/// the list it's built from can never contain the same entity twice the way an arbitrary
+/// caller-supplied list (as in [`LQuery::many_for_each_mut`]) could.
+pub struct CachedQueryIterMut<'w, Q: WorldQuery, F: ReadOnlyQueryFilter> {
+    world: NonNull<RollbackBuffer>,
+    entities: std::vec::IntoIter<Entity>,
+    _marker: PhantomData<(&'w (), Q, F)>,
+}
+
+impl<'w, Q: WorldQuery, F: ReadOnlyQueryFilter> CachedQueryIterMut<'w, Q, F> {
+    /// # Safety
+    /// `world` must be valid for `'w` and callers must ensure this iterator does not alias with
+    /// other mutable access to the same components.
+    unsafe fn new(world: NonNull<RollbackBuffer>, entities: Vec<Entity>) -> Self {
+        Self {
+            world,
+            entities: entities.into_iter(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'w, Q: WorldQuery, F: ReadOnlyQueryFilter> Iterator for CachedQueryIterMut<'w, Q, F> {
+    type Item = <Q::Fetch as Fetch<'w>>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entity = self.entities.next()?;
+            // SAFE: system runs without conflicts with other systems. same-system queries have
+            // runtime borrow checks when they conflict. `entities` holds no duplicates (see the
+            // type doc comment above), so handing out a `Mut` here can't alias a previous one.
+            unsafe {
+                if let Ok(item) = self.world.as_ref().current_world.query_one_unchecked::<Q, F>(entity) {
+                    return Some(item);
+                }
+            }
+        }
+    }
 }
 
 /// Parallel version of QueryIter
-pub struct ParIter<'w, Q: WorldQuery, F: QueryFilter> {
+pub struct ParIter<'w, Q: WorldQuery, F: ReadOnlyQueryFilter> {
     batched_iter: BatchedIter<'w, Q, F>,
 }
 
-impl<'w, Q: WorldQuery, F: QueryFilter> ParIter<'w, Q, F> {
+impl<'w, Q: WorldQuery, F: ReadOnlyQueryFilter> ParIter<'w, Q, F> {
     pub fn new(batched_iter: BatchedIter<'w, Q, F>) -> Self {
         Self { batched_iter }
     }
 }
 
-unsafe impl<'w, Q: WorldQuery, F: QueryFilter> Send for ParIter<'w, Q, F> {}
+unsafe impl<'w, Q: WorldQuery, F: ReadOnlyQueryFilter> Send for ParIter<'w, Q, F> {}
 
-impl<'w, Q: WorldQuery, F: QueryFilter> ParallelIterator<Batch<'w, Q, F>> for ParIter<'w, Q, F> {
+impl<'w, Q: WorldQuery, F: ReadOnlyQueryFilter> ParallelIterator<Batch<'w, Q, F>> for ParIter<'w, Q, F> {
     type Item = <Q::Fetch as Fetch<'w>>::Item;
 
     #[inline]
@@ -238,11 +526,11 @@ impl<'w, Q: WorldQuery, F: QueryFilter> ParallelIterator<Batch<'w, Q, F>> for Pa
 
 pub struct FetchLQuery<Q, F>(PhantomData<(Q, F)>);
 
-impl<'a, Q: WorldQuery, F: QueryFilter> SystemParam for LQuery<'a, Q, F> {
+impl<'a, Q: WorldQuery, F: ReadOnlyQueryFilter> SystemParam for LQuery<'a, Q, F> {
     type Fetch = FetchLQuery<Q, F>;
 }
 
-impl<'a, Q: WorldQuery, F: QueryFilter> FetchSystemParam<'a> for FetchLQuery<Q, F> {
+impl<'a, Q: WorldQuery, F: ReadOnlyQueryFilter> FetchSystemParam<'a> for FetchLQuery<Q, F> {
     type Item = LQuery<'a, Q, F>;
 
     #[inline]
@@ -265,12 +553,28 @@ impl<'a, Q: WorldQuery, F: QueryFilter> FetchSystemParam<'a> for FetchLQuery<Q,
 
         let world = resources
             .get_unsafe_ref::<RollbackBuffer>(ResourceIndex::Global);
+        let matched_archetypes = refresh_query_cache::<Q, F>(
+            &world.as_ref().query_caches,
+            &world.as_ref().current_world,
+        );
         Some(LQuery::new(
             world,
-            archetype_component_access))
+            archetype_component_access,
+            matched_archetypes))
     }
 
     fn init(system_state: &mut SystemState, _world: &World, _resources: &mut Resources) {
+        if system_state.resource_access.is_write(&TypeId::of::<RollbackBuffer>()){
+            panic!(
+                "System '{}' is trying to access Logical Resources while mutating the RollbackBuffer!",
+                system_state.name
+            );
+        }
+        // Registered the same way `FetchLRes`/`FetchLResMut` do, so a later parameter in this
+        // system that takes `ResMut<RollbackBuffer>` panics here too instead of only when that
+        // parameter happens to be initialized first.
+        system_state.resource_access.add_read(TypeId::of::<RollbackBuffer>());
+
         system_state
             .query_archetype_component_accesses
             .push(TypeAccess::default());
@@ -19,6 +19,34 @@ impl<T> L<T>{
     }
 }
 
+/// Pairs a value pointer with its dirty/`mutated` flag pointer behind a single `NonNull`-based
+/// handle, used by every mutable logical-resource param (`LResMut`, `LNonSendMut`). Both pointers
+/// are retained as `NonNull` from construction, and [`LMutHandle::get_mut`] is the *only* place
+/// that derives a `&mut T` from them -- it marks `mutated` in the same call, so the value write
+/// and the mutated write can never happen independently and drift out of sync with each other's
+/// provenance the way two bare, separately-dereferenced raw pointers could.
+struct LMutHandle<T>{
+    value: NonNull<T>,
+    mutated: NonNull<bool>,
+}
+
+impl<T> LMutHandle<T>{
+    unsafe fn new(value: NonNull<T>, mutated: NonNull<bool>) -> Self{
+        Self{ value, mutated }
+    }
+
+    /// Shared access. Does not touch `mutated`.
+    unsafe fn get(&self) -> &T{
+        self.value.as_ref()
+    }
+
+    /// Exclusive access, marking `mutated` in the same call that derives the `&mut T`.
+    unsafe fn get_mut(&mut self) -> &mut T{
+        *self.mutated.as_mut() = true;
+        self.value.as_mut()
+    }
+}
+
 #[derive(Debug)]
 pub struct LRes<'a, T:Resource>{
     value: &'a T,
@@ -81,19 +109,83 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchLRes<T>{
     }
 }
 
+/// Marker for `FetchSystemParam`s that only ever read shared state, mirroring the
+/// `ReadOnlyFetch`/`ReadOnlySystemParamFetch` distinction bevy itself uses to let several
+/// read-only fetches coexist without the exclusivity checks `FetchLResMut` needs. Implemented for
+/// `FetchLRes<T>` but deliberately not for `FetchLResMut<T>`, so [`LResSet`] can only ever be built
+/// out of read-only logical resources.
+pub trait ReadOnlyLResFetch {}
+
+impl<T: Resource> ReadOnlyLResFetch for FetchLRes<T> {}
+
+/// Bundles several read-only `LRes<...>` parameters behind a single `SystemParam`, so a system can
+/// pull many logical resources through one parameter slot instead of one `LRes<T>` per resource.
+pub struct LResSet<'a, T>{
+    value: T,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a, T> Deref for LResSet<'a, T>{
+    type Target = T;
+
+    fn deref(&self) -> &T{
+        &self.value
+    }
+}
+
+pub struct FetchLResSet<T>(PhantomData<T>);
+
+/// Compile-time assertion that `F` is read-only, used by `FetchLResSet::init` so a set can never
+/// be built from anything but `FetchLRes<T>`s even if a future `ReadOnlyLResFetch` impl is added
+/// carelessly.
+fn assert_read_only<F: ReadOnlyLResFetch>() {}
+
+macro_rules! impl_lres_set {
+    ($($t: ident),*) => {
+        impl<'a, $($t: Resource),*> SystemParam for LResSet<'a, ($(LRes<'a, $t>,)*)> {
+            type Fetch = FetchLResSet<($(FetchLRes<$t>,)*)>;
+        }
+
+        impl<'a, $($t: Resource),*> FetchSystemParam<'a> for FetchLResSet<($(FetchLRes<$t>,)*)> {
+            type Item = LResSet<'a, ($(LRes<'a, $t>,)*)>;
+
+            fn init(system_state: &mut SystemState, world: &World, resources: &mut Resources) {
+                $(
+                    assert_read_only::<FetchLRes<$t>>();
+                    FetchLRes::<$t>::init(system_state, world, resources);
+                )*
+            }
+
+            #[inline]
+            unsafe fn get_param(
+                system_state: &'a SystemState,
+                world: &'a World,
+                resources: &'a Resources,
+            ) -> Option<Self::Item> {
+                Some(LResSet{
+                    value: ($(FetchLRes::<$t>::get_param(system_state, world, resources)?,)*),
+                    _marker: PhantomData,
+                })
+            }
+        }
+    };
+}
+
+impl_lres_set!(A);
+impl_lres_set!(A, B);
+impl_lres_set!(A, B, C);
+impl_lres_set!(A, B, C, D);
 
 #[derive(Debug)]
 pub struct LResMut<'a, T:Resource>{
     _marker: PhantomData<&'a T>,
-    value: *mut T,
-    mutated: *mut bool,
+    handle: LMutHandle<T>,
 }
 
 impl<'a, T: Resource> LResMut<'a, T>{
     pub unsafe fn new(value: NonNull<T>, mutated: NonNull<bool>) -> Self {
         Self {
-            value: value.as_ptr(),
-            mutated: mutated.as_ptr(),
+            handle: LMutHandle::new(value, mutated),
             _marker: Default::default(),
         }
     }
@@ -103,16 +195,13 @@ impl<'a, T: Resource> Deref for LResMut<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
-        unsafe{ &*self.value }
+        unsafe{ self.handle.get() }
     }
 }
 
 impl<'a, T: Resource> DerefMut for LResMut<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        unsafe{
-            *self.mutated = true;
-            &mut *self.value
-        }
+        unsafe{ self.handle.get_mut() }
     }
 }
 
@@ -126,7 +215,7 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchLResMut<T>{
     type Item = LResMut<'a, T>;
 
     fn init(system_state: &mut SystemState, _world: &World, resources: &mut Resources) {
-        if system_state.resource_access.is_read_or_write(&TypeId::of::<T>()) {
+        if system_state.resource_access.is_read_or_write(&TypeId::of::<L<T>>()) {
             panic!(
                 "System '{}' has a `LRes<{res}>` or `LResMut<{res}>` parameter that conflicts with \
                 another parameter with mutable access to the same `{res}` resource.",
@@ -141,7 +230,7 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchLResMut<T>{
             );
         }
         system_state.resource_access.add_read(TypeId::of::<RollbackBuffer>());
-        system_state.resource_access.add_write(TypeId::of::<T>());
+        system_state.resource_access.add_write(TypeId::of::<L<T>>());
     }
 
     #[inline]
@@ -156,4 +245,148 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchLResMut<T>{
             LResMut::new(value, mutated),
         )
     }
+}
+
+/// Read-only logical access to a thread-local resource in `RollbackBuffer::current_resources`.
+/// The `LNonSend`/`LNonSendMut` counterpart of `LRes`/`LResMut`, for `!Send` resources (render
+/// handles, OS resources) that can't be bounded on `Resource` the way `LRes` is.
+#[derive(Debug)]
+pub struct LNonSend<'a, T: 'static>{
+    value: &'a T,
+}
+
+impl<'a, T: 'static> LNonSend<'a, T>{
+    pub unsafe fn new(value: NonNull<T>) -> Self{
+        Self{
+            value: &*value.as_ptr(),
+        }
+    }
+}
+
+impl<'a, T: 'static> Deref for LNonSend<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<'a, T: 'static>  SystemParam for LNonSend<'a, T>{
+    type Fetch = FetchLNonSend<T>;
+}
+
+pub struct FetchLNonSend<T>(PhantomData<T>);
+
+impl<'a, T: 'static> FetchSystemParam<'a> for FetchLNonSend<T>{
+    type Item = LNonSend<'a, T>;
+
+    fn init(system_state: &mut SystemState, _world: &World, resources: &mut Resources) {
+        if system_state.resource_access.is_write(&TypeId::of::<L<T>>()){
+            panic!(
+                "System '{}' has a `LNonSend<{res}>` parameter that conflicts with \
+                another parameter with mutable access to the same `{res}` resource.",
+                system_state.name,
+                res = std::any::type_name::<T>()
+            );
+        }
+        if system_state.resource_access.is_write(&TypeId::of::<RollbackBuffer>()){
+            panic!(
+                "System '{}' is trying to access Logical Resources while mutating the RollbackBuffer!",
+                system_state.name
+            );
+        }
+        system_state.resource_access.add_read(TypeId::of::<RollbackBuffer>());
+        system_state.resource_access.add_read(TypeId::of::<L<T>>());
+        // `T` isn't `Send`, so -- like bevy's own `NonSend` -- force this system onto the main
+        // thread instead of letting the scheduler run it on an arbitrary worker.
+        system_state.is_non_send = true;
+    }
+
+    #[inline]
+    unsafe fn get_param(
+        _system_state: &'a SystemState,
+        _world: &'a World,
+        resources: &'a Resources,
+    ) -> Option<Self::Item> {
+        let rollback_buffer = resources.get::<RollbackBuffer>().expect("Couldn't acquire RollbackBuffer!");
+        Some(
+            LNonSend::new(rollback_buffer.current_resources.get_non_send_unsafe_ref::<T>(ResourceIndex::Global)),
+        )
+    }
+}
+
+/// Mutable logical access to a thread-local resource in `RollbackBuffer::current_resources`. See
+/// [`LNonSend`].
+#[derive(Debug)]
+pub struct LNonSendMut<'a, T: 'static>{
+    _marker: PhantomData<&'a T>,
+    handle: LMutHandle<T>,
+}
+
+impl<'a, T: 'static> LNonSendMut<'a, T>{
+    pub unsafe fn new(value: NonNull<T>, mutated: NonNull<bool>) -> Self {
+        Self {
+            handle: LMutHandle::new(value, mutated),
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<'a, T: 'static> Deref for LNonSendMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe{ self.handle.get() }
+    }
+}
+
+impl<'a, T: 'static> DerefMut for LNonSendMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe{ self.handle.get_mut() }
+    }
+}
+
+impl<'a, T: 'static>  SystemParam for LNonSendMut<'a, T>{
+    type Fetch = FetchLNonSendMut<T>;
+}
+
+pub struct FetchLNonSendMut<T>(PhantomData<T>);
+
+impl<'a, T: 'static> FetchSystemParam<'a> for FetchLNonSendMut<T>{
+    type Item = LNonSendMut<'a, T>;
+
+    fn init(system_state: &mut SystemState, _world: &World, resources: &mut Resources) {
+        if system_state.resource_access.is_read_or_write(&TypeId::of::<L<T>>()) {
+            panic!(
+                "System '{}' has a `LNonSend<{res}>` or `LNonSendMut<{res}>` parameter that conflicts with \
+                another parameter with mutable access to the same `{res}` resource.",
+                system_state.name,
+                res = std::any::type_name::<T>()
+            );
+        }
+        if system_state.resource_access.is_write(&TypeId::of::<RollbackBuffer>()){
+            panic!(
+                "System '{}' is trying to access Logical Resources while mutating the RollbackBuffer!",
+                system_state.name
+            );
+        }
+        system_state.resource_access.add_read(TypeId::of::<RollbackBuffer>());
+        system_state.resource_access.add_write(TypeId::of::<L<T>>());
+        // `T` isn't `Send`, so -- like bevy's own `NonSendMut` -- force this system onto the main
+        // thread instead of letting the scheduler run it on an arbitrary worker.
+        system_state.is_non_send = true;
+    }
+
+    #[inline]
+    unsafe fn get_param(
+        _system_state: &'a SystemState,
+        _world: &'a World,
+        resources: &'a Resources,
+    ) -> Option<Self::Item> {
+        let rollback_buffer = resources.get::<RollbackBuffer>().expect("Couldn't acquire RollbackBuffer!");
+        let (value, _added, mutated) = rollback_buffer.current_resources.get_non_send_unsafe_ref_with_added_and_mutated::<T>(ResourceIndex::Global);
+        Some(
+            LNonSendMut::new(value, mutated),
+        )
+    }
 }
\ No newline at end of file
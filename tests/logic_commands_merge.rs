@@ -0,0 +1,80 @@
+// Regression test for `LogicCommands::merge`'s ordering guarantee (see `src/commands.rs`):
+// several `LogicCommandsBuilder`s stamped with insertion indices drawn up front, in registration
+// order, must always merge and apply in that same registration order, no matter what order the
+// threads that built them actually finished in. Builds four `LogicCommands` on separate threads
+// that intentionally finish in the *reverse* of their registration order, merges them, applies the
+// result, and asserts the spawned entities came out in registration order regardless. Run once the
+// workspace has a manifest:
+//
+//     cargo test --test logic_commands_merge
+
+use bevy::ecs::Stage;
+use bevy::prelude::*;
+use bevy_rollback_archive::{LQuery, LogicCommands, RollbackBuffer, RollbackStage};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+struct Tag(usize);
+
+fn always_run() -> ShouldRun {
+    ShouldRun::Yes
+}
+
+fn record_tags(observed: Arc<Mutex<Vec<usize>>>) -> impl FnMut(LQuery<&Tag>) {
+    move |query: LQuery<&Tag>| {
+        *observed.lock().unwrap() = query.iter().map(|tag| tag.0).collect();
+    }
+}
+
+#[test]
+fn merge_applies_in_registration_order_regardless_of_thread_finish_order() {
+    const SYSTEM_COUNT: usize = 4;
+
+    let rollback_buffer = RollbackBuffer::new(8);
+
+    // Drawn up front, in registration order -- the way a caller draws `next_logic_system_index`
+    // once per system at schedule-definition time (e.g. right before `add_logic_system`), never
+    // at system run time.
+    let insertion_indices: Vec<usize> = (0..SYSTEM_COUNT)
+        .map(|_| rollback_buffer.next_logic_system_index())
+        .collect();
+
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|scope| {
+        for (registration_order, &insertion_index) in insertion_indices.iter().enumerate() {
+            let rollback_buffer = &rollback_buffer;
+            let tx = tx.clone();
+            // Sleep in reverse registration order, so the *last*-registered system's thread is
+            // actually the *first* one to finish building its commands.
+            let sleep_ms = (SYSTEM_COUNT - registration_order) as u64 * 20;
+            scope.spawn(move || {
+                thread::sleep(Duration::from_millis(sleep_ms));
+                let mut builder = rollback_buffer.get_logic_commands_builder(insertion_index);
+                builder.spawn((Tag(registration_order),));
+                tx.send(builder.build()).unwrap();
+            });
+        }
+    });
+    drop(tx);
+    // Collected in actual thread-finish order, which is the reverse of registration order --
+    // `merge` must re-sort this back into registration order before applying it.
+    let logic_commands: Vec<LogicCommands> = rx.iter().collect();
+    let merged = LogicCommands::merge(logic_commands);
+
+    let mut world = World::new();
+    let mut resources = Resources::default();
+    resources.insert(rollback_buffer);
+    Box::new(merged).write(&mut world, &mut resources);
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    let mut schedule = Schedule::default().with_stage("observe", SystemStage::parallel());
+    schedule.add_system_to_stage("observe", record_tags(observed.clone()).system());
+    let mut stage = RollbackStage::with_schedule(schedule).with_run_criteria(always_run.system());
+
+    stage.initialize(&mut world, &mut resources);
+    stage.run(&mut world, &mut resources);
+
+    assert_eq!(*observed.lock().unwrap(), vec![0, 1, 2, 3]);
+}
@@ -0,0 +1,9 @@
+// Drives the `trybuild` fixtures under `tests/ui/`. Run once the workspace has a manifest:
+//
+//     cargo test --test ui
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/mutating_filter_rejected.rs");
+}
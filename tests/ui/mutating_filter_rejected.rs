@@ -0,0 +1,14 @@
+// UI fixture for `LQuery`'s `F: ReadOnlyQueryFilter` bound. A filter parameter that fetches
+// component data mutably must not type-check as `LQuery`'s `F`, since that would let a filter
+// alias a mutable `Q` fetch. Driven by the `trybuild` runner in `tests/ui.rs`.
+
+use bevy::prelude::*;
+use bevy_rollback_archive::LQuery;
+
+struct Position(f32);
+
+// `Mut<Position>` fetches mutably, so it does not implement `ReadOnlyQueryFilter` and using it
+// as `LQuery`'s filter parameter must fail to compile.
+fn uses_mutating_filter(_query: LQuery<&Position, Mut<Position>>) {}
+
+fn main() {}